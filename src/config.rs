@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::sink;
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub(crate) enum SinkConfig {
+    Stdout,
+    File {
+        path: PathBuf,
+        max_bytes: u64,
+    },
+    Tcp {
+        addr: String,
+    },
+    Udp {
+        addr: String,
+    },
+    Http {
+        url: String,
+        batch_size: usize,
+        flush_interval_ms: u64,
+        max_buffer_size: usize,
+    },
+}
+
+impl SinkConfig {
+    pub(crate) fn into_sink(self) -> Box<dyn sink::Sink> {
+        match self {
+            SinkConfig::Stdout => Box::new(sink::StdoutSink::default()),
+            SinkConfig::File { path, max_bytes } => {
+                match sink::FileSink::new(path.clone(), max_bytes) {
+                    Ok(file_sink) => Box::new(file_sink),
+                    Err(err) => {
+                        eprintln!("Failed to open log file sink {:?}: {}", path, err);
+                        Box::new(sink::StdoutSink::default())
+                    }
+                }
+            }
+            SinkConfig::Tcp { addr } => Box::new(sink::TcpSink::new(addr)),
+            SinkConfig::Udp { addr } => Box::new(sink::UdpSink::new(addr)),
+            SinkConfig::Http {
+                url,
+                batch_size,
+                flush_interval_ms,
+                max_buffer_size,
+            } => Box::new(sink::HttpSink::new(
+                url,
+                batch_size,
+                Duration::from_millis(flush_interval_ms),
+                max_buffer_size,
+            )),
+        }
+    }
+
+    fn file_from(entries: &HashMap<String, String>) -> Result<Self, String> {
+        let path = entries
+            .get("path")
+            .ok_or_else(|| String::from("sink.file requires a 'path'"))?;
+
+        let max_bytes = match entries.get("max_bytes") {
+            Some(value) => value
+                .parse::<u64>()
+                .map_err(|err| format!("Invalid max_bytes '{}': {}", value, err))?,
+            None => 10 * 1024 * 1024,
+        };
+
+        Ok(SinkConfig::File {
+            path: PathBuf::from(path),
+            max_bytes,
+        })
+    }
+
+    fn tcp_from(entries: &HashMap<String, String>) -> Result<Self, String> {
+        let addr = entries
+            .get("addr")
+            .ok_or_else(|| String::from("sink.tcp requires an 'addr'"))?;
+
+        Ok(SinkConfig::Tcp { addr: addr.clone() })
+    }
+
+    fn udp_from(entries: &HashMap<String, String>) -> Result<Self, String> {
+        let addr = entries
+            .get("addr")
+            .ok_or_else(|| String::from("sink.udp requires an 'addr'"))?;
+
+        Ok(SinkConfig::Udp { addr: addr.clone() })
+    }
+
+    fn http_from(entries: &HashMap<String, String>) -> Result<Self, String> {
+        let url = entries
+            .get("url")
+            .ok_or_else(|| String::from("sink.http requires a 'url'"))?;
+
+        let batch_size = match entries.get("batch_size") {
+            Some(value) => value
+                .parse::<usize>()
+                .map_err(|err| format!("Invalid batch_size '{}': {}", value, err))?,
+            None => 20,
+        };
+
+        let flush_interval_ms = match entries.get("flush_interval_ms") {
+            Some(value) => value
+                .parse::<u64>()
+                .map_err(|err| format!("Invalid flush_interval_ms '{}': {}", value, err))?,
+            None => 1000,
+        };
+
+        let max_buffer_size = match entries.get("max_buffer_size") {
+            Some(value) => value
+                .parse::<usize>()
+                .map_err(|err| format!("Invalid max_buffer_size '{}': {}", value, err))?,
+            None => 1000,
+        };
+
+        Ok(SinkConfig::Http {
+            url: url.clone(),
+            batch_size,
+            flush_interval_ms,
+            max_buffer_size,
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub(crate) const DEFAULT_CHANNEL_CAPACITY: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OverflowPolicy {
+    Block,
+    DropNewest,
+    DropOldest,
+}
+
+impl OverflowPolicy {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "block" => Some(OverflowPolicy::Block),
+            "drop_newest" => Some(OverflowPolicy::DropNewest),
+            "drop_oldest" => Some(OverflowPolicy::DropOldest),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Config {
+    pub(crate) format: String,
+    pub(crate) level: String,
+    pub(crate) channel_capacity: usize,
+    pub(crate) overflow_policy: OverflowPolicy,
+    pub(crate) sinks: Vec<SinkConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            format: String::from("flat"),
+            level: String::from("INFO"),
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            overflow_policy: OverflowPolicy::Block,
+            sinks: vec![SinkConfig::Stdout],
+        }
+    }
+}
+
+impl Config {
+    pub(crate) fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read config file {:?}: {}", path, err))?;
+
+        let sections = parse_sections(&contents)?;
+        let mut config = Self::default();
+        let mut sinks = Vec::new();
+
+        for (name, entries) in &sections {
+            match name.as_str() {
+                "logger" => config.apply_logger_section(entries)?,
+                "sink.stdout" => sinks.push(SinkConfig::Stdout),
+                "sink.file" => sinks.push(SinkConfig::file_from(entries)?),
+                "sink.tcp" => sinks.push(SinkConfig::tcp_from(entries)?),
+                "sink.udp" => sinks.push(SinkConfig::udp_from(entries)?),
+                "sink.http" => sinks.push(SinkConfig::http_from(entries)?),
+                _ => {}
+            }
+        }
+
+        if !sinks.is_empty() {
+            config.sinks = sinks;
+        }
+
+        Ok(config)
+    }
+
+    fn apply_logger_section(&mut self, entries: &HashMap<String, String>) -> Result<(), String> {
+        if let Some(format) = entries.get("format") {
+            match format.to_lowercase().as_str() {
+                name @ ("flat" | "gelf" | "syslog") => self.format = name.to_owned(),
+                other => return Err(format!("Unknown output format '{}'", other)),
+            }
+        }
+
+        if let Some(level) = entries.get("level") {
+            match level.to_uppercase().as_str() {
+                name @ ("ERRO" | "WARN" | "INFO") => self.level = name.to_owned(),
+                other => return Err(format!("Unknown log level '{}'", other)),
+            }
+        }
+
+        if let Some(capacity) = entries.get("channel_capacity") {
+            self.channel_capacity = capacity
+                .parse::<usize>()
+                .map_err(|err| format!("Invalid channel_capacity '{}': {}", capacity, err))?;
+        }
+
+        if let Some(policy) = entries.get("overflow_policy") {
+            self.overflow_policy = OverflowPolicy::parse(&policy.to_lowercase())
+                .ok_or_else(|| format!("Unknown overflow policy '{}'", policy))?;
+        }
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// A minimal INI-style parser: `[section]` headers followed by `key = value`
+// lines, matching the flat key/value style Janus plugin configs already use.
+fn parse_sections(input: &str) -> Result<Vec<(String, HashMap<String, String>)>, String> {
+    let mut sections = Vec::new();
+    let mut current: Option<(String, HashMap<String, String>)> = None;
+
+    for (number, raw_line) in input.lines().enumerate() {
+        let line = raw_line.trim();
+
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            if let Some(section) = current.take() {
+                sections.push(section);
+            }
+
+            current = Some((name.to_owned(), HashMap::new()));
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("Malformed config line {}: '{}'", number + 1, raw_line))?;
+
+        let section = current.as_mut().ok_or_else(|| {
+            format!(
+                "Config line {} found before any section header: '{}'",
+                number + 1,
+                raw_line
+            )
+        })?;
+
+        section.1.insert(
+            key.trim().to_owned(),
+            value.trim().trim_matches('"').to_owned(),
+        );
+    }
+
+    if let Some(section) = current.take() {
+        sections.push(section);
+    }
+
+    Ok(sections)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sections_reads_keys_into_their_section() {
+        let sections = parse_sections(
+            "[logger]\nformat = gelf\nlevel = \"WARN\"\n\n[sink.tcp]\naddr = 127.0.0.1:9000\n",
+        )
+        .expect("should parse");
+
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].0, "logger");
+        assert_eq!(sections[0].1.get("format").map(String::as_str), Some("gelf"));
+        assert_eq!(sections[0].1.get("level").map(String::as_str), Some("WARN"));
+        assert_eq!(sections[1].0, "sink.tcp");
+        assert_eq!(
+            sections[1].1.get("addr").map(String::as_str),
+            Some("127.0.0.1:9000")
+        );
+    }
+
+    #[test]
+    fn parse_sections_skips_comments_and_blank_lines() {
+        let sections = parse_sections("# comment\n; also a comment\n\n[logger]\nlevel = INFO\n")
+            .expect("should parse");
+
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].1.len(), 1);
+    }
+
+    #[test]
+    fn parse_sections_rejects_a_key_before_any_section_header() {
+        let err = parse_sections("level = INFO\n").unwrap_err();
+        assert!(err.contains("found before any section header"));
+    }
+
+    #[test]
+    fn parse_sections_rejects_a_line_without_an_equals_sign() {
+        let err = parse_sections("[logger]\nnotakeyvalue\n").unwrap_err();
+        assert!(err.contains("Malformed config line"));
+    }
+
+    #[test]
+    fn apply_logger_section_rejects_an_unknown_level() {
+        let mut entries = HashMap::new();
+        entries.insert(String::from("level"), String::from("VERBOSE"));
+
+        let mut config = Config::default();
+        let err = config.apply_logger_section(&entries).unwrap_err();
+
+        assert!(err.contains("Unknown log level"));
+    }
+
+    #[test]
+    fn apply_logger_section_rejects_an_unparseable_channel_capacity() {
+        let mut entries = HashMap::new();
+        entries.insert(String::from("channel_capacity"), String::from("not-a-number"));
+
+        let mut config = Config::default();
+        let err = config.apply_logger_section(&entries).unwrap_err();
+
+        assert!(err.contains("Invalid channel_capacity"));
+    }
+}
@@ -5,13 +5,24 @@ extern crate serde_derive;
 
 #[macro_use]
 mod janus_logger;
+mod config;
+mod sink;
+mod stats;
 
 use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::RecvTimeoutError;
+
+use sink::Sink;
+use stats::Stats;
 
 use chrono::{DateTime, Local, NaiveDateTime, Utc};
 use regex::Regex;
-use serde_json::{json, Value as JsonValue};
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
 
 ////////////////////////////////////////////////////////////////////////////////
 
@@ -39,6 +50,161 @@ enum SourceWithTags {
     Unknown { logger_error: String },
 }
 
+impl SourceWithTags {
+    fn source_name(&self) -> &'static str {
+        match self {
+            SourceWithTags::Core(_) => "core",
+            SourceWithTags::Conference(_) => "conference",
+            SourceWithTags::Unknown { .. } => "unknown",
+        }
+    }
+
+    fn into_gelf_fields(self) -> JsonMap<String, JsonValue> {
+        let mut fields = JsonMap::new();
+        fields.insert(gelf_key("source"), JsonValue::from(self.source_name()));
+
+        match self {
+            SourceWithTags::Core(tags) => {
+                if let Some(handle_id) = tags.handle_id {
+                    fields.insert(gelf_key("handle_id"), JsonValue::from(handle_id));
+                }
+            }
+            SourceWithTags::Conference(tags) => {
+                if let Some(handle_id) = tags.handle_id {
+                    fields.insert(gelf_key("handle_id"), JsonValue::from(handle_id));
+                }
+
+                if let Some(rtc_id) = tags.rtc_id {
+                    fields.insert(gelf_key("rtc_id"), JsonValue::from(rtc_id));
+                }
+
+                if let Some(agent_id) = tags.agent_id {
+                    fields.insert(gelf_key("agent_id"), JsonValue::from(agent_id));
+                }
+
+                if let Some(transaction) = tags.transaction {
+                    fields.insert(gelf_key("transaction"), JsonValue::from(transaction));
+                }
+            }
+            SourceWithTags::Unknown { logger_error } => {
+                fields.insert(gelf_key("logger_error"), JsonValue::from(logger_error));
+            }
+        }
+
+        fields
+    }
+}
+
+// GELF reserves unprefixed keys for its own fields and forbids `_id` outright,
+// so additional fields are prefixed with an underscore and `_id` is renamed.
+fn gelf_key(name: &str) -> String {
+    let key = format!("_{}", name);
+
+    if key == "_id" {
+        format!("_{}_field", name)
+    } else {
+        key
+    }
+}
+
+impl SourceWithTags {
+    fn syslog_fields(&self) -> Vec<(&'static str, String)> {
+        match self {
+            SourceWithTags::Core(tags) => tags
+                .handle_id
+                .map(|handle_id| vec![("handle_id", handle_id.to_string())])
+                .unwrap_or_default(),
+            SourceWithTags::Conference(tags) => {
+                let mut fields = Vec::new();
+
+                if let Some(handle_id) = tags.handle_id {
+                    fields.push(("handle_id", handle_id.to_string()));
+                }
+
+                if let Some(rtc_id) = &tags.rtc_id {
+                    fields.push(("rtc_id", rtc_id.clone()));
+                }
+
+                if let Some(agent_id) = &tags.agent_id {
+                    fields.push(("agent_id", agent_id.clone()));
+                }
+
+                if let Some(transaction) = &tags.transaction {
+                    fields.push(("transaction", transaction.clone()));
+                }
+
+                fields
+            }
+            SourceWithTags::Unknown { logger_error } => {
+                vec![("logger_error", logger_error.clone())]
+            }
+        }
+    }
+
+    // Builds an RFC 5424 SD-ELEMENT, e.g. `[janus@32473 handle_id="..." rtc_id="..."]`.
+    fn to_syslog_structured_data(&self) -> String {
+        let fields = self.syslog_fields();
+
+        if fields.is_empty() {
+            return String::from("-");
+        }
+
+        let mut sd = format!("[janus@{}", SYSLOG_ENTERPRISE_NUMBER);
+
+        for (key, value) in fields {
+            sd.push_str(&format!(" {}=\"{}\"", key, escape_syslog_value(&value)));
+        }
+
+        sd.push(']');
+        sd
+    }
+}
+
+// Escapes `\`, `"` and `]` per RFC 5424 section 6.3.3, in that order so the
+// backslashes introduced by the later replacements aren't themselves escaped.
+fn escape_syslog_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace(']', "\\]")
+}
+
+const SYSLOG_FACILITY_LOCAL0: u8 = 16;
+const SYSLOG_ENTERPRISE_NUMBER: u32 = 32473;
+
+fn render_syslog_frame(
+    level: &str,
+    host: &str,
+    app_name: &str,
+    source_with_tags: &SourceWithTags,
+    timestamp: &str,
+    msg: &str,
+) -> String {
+    let pri = u16::from(SYSLOG_FACILITY_LOCAL0) * 8 + u16::from(Message::syslog_severity(level));
+    let proc_id = std::process::id();
+    let msg_id = source_with_tags.source_name().to_uppercase();
+    let structured_data = source_with_tags.to_syslog_structured_data();
+    // MSG must stay on one line so line-delimited sinks (stdout/file/TCP) don't
+    // split a single frame into several unterminated ones.
+    let msg = msg.replace("\r\n", "\\n").replace(['\r', '\n'], "\\n");
+
+    format!(
+        "<{}>1 {} {} {} {} {} {} {}",
+        pri, timestamp, host, app_name, proc_id, msg_id, structured_data, msg
+    )
+}
+
+#[derive(Debug, Serialize)]
+struct GelfMessage {
+    version: &'static str,
+    host: String,
+    short_message: String,
+    timestamp: f64,
+    level: u8,
+    #[serde(flatten)]
+    additional_fields: JsonMap<String, JsonValue>,
+}
+
 #[derive(Debug, Default, Serialize)]
 struct CoreTags {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -91,6 +257,53 @@ impl Message {
         }
     }
 
+    fn to_gelf_message(&self, host: &str) -> GelfMessage {
+        let (level, rest) = self.extract_level();
+
+        let (source_with_tags, msg) = match Self::extract_source_with_tags(rest) {
+            Ok((source_with_tags, rest)) => (source_with_tags, rest.trim()),
+            Err(err) => (SourceWithTags::Unknown { logger_error: err }, rest.trim()),
+        };
+
+        GelfMessage {
+            version: "1.1",
+            host: host.to_owned(),
+            short_message: msg.to_owned(),
+            timestamp: self.timestamp as f64 / 1_000_000.0,
+            level: Self::syslog_severity(level),
+            additional_fields: source_with_tags.into_gelf_fields(),
+        }
+    }
+
+    fn syslog_severity(level: &str) -> u8 {
+        match level {
+            "ERRO" => 3,
+            "WARN" => 4,
+            _ => 6,
+        }
+    }
+
+    fn to_syslog_message(&self, host: &str, app_name: &str) -> String {
+        let timestamp = self.timestamp().to_rfc3339();
+        let (level, rest) = self.extract_level();
+
+        let (source_with_tags, msg) = match Self::extract_source_with_tags(rest) {
+            Ok((source_with_tags, rest)) => (source_with_tags, rest.trim()),
+            Err(err) => (SourceWithTags::Unknown { logger_error: err }, rest.trim()),
+        };
+
+        render_syslog_frame(level, host, app_name, &source_with_tags, &timestamp, msg)
+    }
+
+    fn source_name(&self) -> &'static str {
+        let (_level, rest) = self.extract_level();
+
+        match Self::extract_source_with_tags(rest) {
+            Ok((source_with_tags, _rest)) => source_with_tags.source_name(),
+            Err(_err) => "unknown",
+        }
+    }
+
     fn timestamp(&self) -> DateTime<Utc> {
         let secs = self.timestamp / 1000000;
         let nsecs = self.timestamp % 1000000 * 1000;
@@ -156,35 +369,359 @@ impl Message {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+const APP_NAME: &str = env!("CARGO_PKG_NAME");
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum OutputFormat {
+    Flat,
+    Gelf,
+    Syslog,
+}
+
+impl Message {
+    fn dump(&self, format: OutputFormat, host: &str) -> serde_json::Result<String> {
+        match format {
+            OutputFormat::Flat => serde_json::to_string(&self.to_json_message()),
+            OutputFormat::Gelf => serde_json::to_string(&self.to_gelf_message(host)),
+            OutputFormat::Syslog => Ok(self.to_syslog_message(host, APP_NAME)),
+        }
+    }
+}
+
+// Renders a logger-originated (rather than Janus-originated) line, such as a
+// config error or a drop-rate warning, in whichever format is configured.
+fn dump_synthetic(
+    format: OutputFormat,
+    host: &str,
+    level: &'static str,
+    source_with_tags: SourceWithTags,
+    msg: &str,
+) -> serde_json::Result<String> {
+    match format {
+        OutputFormat::Flat => serde_json::to_string(&JsonMessage {
+            ts: Local::now().to_rfc3339(),
+            level,
+            source_with_tags,
+            msg,
+        }),
+        OutputFormat::Gelf => {
+            let now = Utc::now();
+            let timestamp =
+                now.timestamp() as f64 + f64::from(now.timestamp_subsec_micros()) / 1_000_000.0;
+
+            serde_json::to_string(&GelfMessage {
+                version: "1.1",
+                host: host.to_owned(),
+                short_message: msg.to_owned(),
+                timestamp,
+                level: Message::syslog_severity(level),
+                additional_fields: source_with_tags.into_gelf_fields(),
+            })
+        }
+        OutputFormat::Syslog => Ok(render_syslog_frame(
+            level,
+            host,
+            APP_NAME,
+            &source_with_tags,
+            &Utc::now().to_rfc3339(),
+            msg,
+        )),
+    }
+}
+
+fn emit_synthetic(
+    format: OutputFormat,
+    host: &str,
+    level: &'static str,
+    source_with_tags: SourceWithTags,
+    msg: &str,
+    sinks: &mut [Box<dyn Sink>],
+) {
+    if let Ok(dumped) = dump_synthetic(format, host, level, source_with_tags, msg) {
+        for sink in sinks.iter_mut() {
+            sink.write(&dumped);
+        }
+    }
+}
+
+const SUPPORTED_VERBS: &[&str] = &["set_level", "get_stats", "capabilities", "version"];
+const SUPPORTED_FORMATS: &[&str] = &["flat", "gelf", "syslog"];
+
+fn unknown_verb_error(verb: &str) -> JsonValue {
+    json!({
+        "error": format!("Unknown request verb '{}'", verb),
+        "supported_verbs": SUPPORTED_VERBS,
+    })
+}
+
+const STATS_REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
 #[derive(Debug)]
 pub(crate) struct JanusConferenceLogger {
     tx: crossbeam_channel::Sender<Message>,
+    rx: crossbeam_channel::Receiver<Message>,
+    overflow_policy: config::OverflowPolicy,
+    min_level: Arc<AtomicU8>,
+    stats: Arc<Stats>,
+}
+
+impl JanusConferenceLogger {
+    fn handle_set_level(&self, request: &JsonValue) -> JsonValue {
+        let level = match request.get("level").and_then(JsonValue::as_str) {
+            Some(level) => level,
+            None => return json!({"error": "Missing 'level' field"}),
+        };
+
+        match level.to_uppercase().as_str() {
+            name @ ("ERRO" | "WARN" | "INFO") => {
+                self.min_level
+                    .store(stats::level_rank(name), Ordering::Relaxed);
+                json!({"result": "ok", "level": name})
+            }
+            _ => json!({"error": format!("Unknown level '{}'", level)}),
+        }
+    }
+
+    fn capabilities(&self) -> JsonValue {
+        json!({
+            "result": "ok",
+            "verbs": SUPPORTED_VERBS,
+            "formats": SUPPORTED_FORMATS,
+            "level": stats::level_name(self.min_level.load(Ordering::Relaxed)),
+        })
+    }
 }
 
 impl janus_logger::JanusLogger for JanusConferenceLogger {
-    fn new(_server_name: &str, _config_path: &Path) -> Self {
-        let (tx, rx) = crossbeam_channel::unbounded::<Message>();
+    fn new(server_name: &str, config_path: &Path) -> Self {
+        let (config, config_error) = match config::Config::load(config_path) {
+            Ok(config) => (config, None),
+            Err(err) => (config::Config::default(), Some(err)),
+        };
+
+        let (tx, rx) = crossbeam_channel::bounded::<Message>(config.channel_capacity);
+
+        let mut sinks: Vec<Box<dyn Sink>> = config
+            .sinks
+            .clone()
+            .into_iter()
+            .map(config::SinkConfig::into_sink)
+            .collect();
+
+        let format = match config.format.as_str() {
+            "gelf" => OutputFormat::Gelf,
+            "syslog" => OutputFormat::Syslog,
+            _ => OutputFormat::Flat,
+        };
+
+        let server_name = server_name.to_owned();
+        let min_level = Arc::new(AtomicU8::new(stats::level_rank(&config.level)));
+        let stats = Arc::new(Stats::default());
+
+        if let Some(err) = config_error {
+            emit_synthetic(
+                format,
+                &server_name,
+                "ERRO",
+                SourceWithTags::Unknown { logger_error: err },
+                "Failed to load config, falling back to defaults",
+                &mut sinks,
+            );
+        }
 
-        thread::spawn(move || {
-            while let Ok(message) = rx.recv() {
-                let json_message = message.to_json_message();
+        let worker_min_level = min_level.clone();
+        let worker_stats = stats.clone();
+        let worker_server_name = server_name.clone();
+        let rx_for_eviction = rx.clone();
 
-                if let Ok(dumped_message) = serde_json::to_string(&json_message) {
-                    println!("{}", dumped_message);
+        thread::spawn(move || {
+            let mut last_reported_dropped = 0u64;
+            let mut last_report_at = Instant::now();
+
+            loop {
+                // A steady stream of lines would keep recv_timeout from ever
+                // timing out, so the idle/report step is forced once the
+                // report interval has passed.
+                let wait = STATS_REPORT_INTERVAL.saturating_sub(last_report_at.elapsed());
+                let received = if wait.is_zero() {
+                    Err(RecvTimeoutError::Timeout)
+                } else {
+                    rx.recv_timeout(wait)
+                };
+
+                match received {
+                    Ok(message) => {
+                        let (level, _rest) = message.extract_level();
+
+                        if stats::level_rank(level) > worker_min_level.load(Ordering::Relaxed) {
+                            worker_stats.record_filtered();
+                            continue;
+                        }
+
+                        worker_stats.record_source(message.source_name());
+
+                        match message.dump(format, &worker_server_name) {
+                            Ok(dumped_message) => {
+                                worker_stats.record_emitted();
+
+                                for sink in sinks.iter_mut() {
+                                    sink.write(&dumped_message);
+                                }
+                            }
+                            Err(_err) => worker_stats.record_serialization_error(),
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {
+                        last_report_at = Instant::now();
+
+                        for sink in sinks.iter_mut() {
+                            sink.tick();
+                        }
+
+                        let dropped = worker_stats.dropped();
+                        let delta = dropped.saturating_sub(last_reported_dropped);
+
+                        if delta > 0 {
+                            let msg = format!(
+                                "{} messages dropped due to overflow in the last {}s",
+                                delta,
+                                STATS_REPORT_INTERVAL.as_secs()
+                            );
+
+                            emit_synthetic(
+                                format,
+                                &worker_server_name,
+                                "WARN",
+                                SourceWithTags::Core(CoreTags::default()),
+                                &msg,
+                                &mut sinks,
+                            );
+                            last_reported_dropped = dropped;
+                        }
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
                 }
             }
         });
 
-        Self { tx }
+        Self {
+            tx,
+            rx: rx_for_eviction,
+            overflow_policy: config.overflow_policy,
+            min_level,
+            stats,
+        }
     }
 
     fn incoming_logline(&self, timestamp: i64, line: &str) {
-        let _result = self.tx.send(Message::new(timestamp, line));
+        let message = Message::new(timestamp, line);
+        self.stats.record_received();
+
+        match self.overflow_policy {
+            config::OverflowPolicy::Block => {
+                let _result = self.tx.send(message);
+            }
+            config::OverflowPolicy::DropNewest => {
+                if self.tx.try_send(message).is_err() {
+                    self.stats.record_dropped();
+                }
+            }
+            config::OverflowPolicy::DropOldest => {
+                if let Err(crossbeam_channel::TrySendError::Full(message)) =
+                    self.tx.try_send(message)
+                {
+                    let _ = self.rx.try_recv();
+                    self.stats.record_dropped();
+                    let _ = self.tx.try_send(message);
+                }
+            }
+        }
     }
 
-    fn handle_request(&self, _request: &JsonValue) -> JsonValue {
-        json!({"error": "not implemented"})
+    fn handle_request(&self, request: &JsonValue) -> JsonValue {
+        let verb = request
+            .get("janus")
+            .or_else(|| request.get("request"))
+            .and_then(JsonValue::as_str);
+
+        match verb {
+            Some("set_level") => self.handle_set_level(request),
+            Some("get_stats") => self.stats.snapshot(),
+            Some("capabilities") | Some("version") => self.capabilities(),
+            Some(other) => unknown_verb_error(other),
+            None => unknown_verb_error(""),
+        }
     }
 }
 
 define_logger!(JanusConferenceLogger);
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_syslog_value_escapes_backslash_quote_and_bracket_in_order() {
+        assert_eq!(escape_syslog_value(r#"a\b"c]d"#), r#"a\\b\"c\]d"#);
+    }
+
+    #[test]
+    fn escape_syslog_value_leaves_plain_text_untouched() {
+        assert_eq!(escape_syslog_value("plain text"), "plain text");
+    }
+
+    #[test]
+    fn gelf_key_prefixes_with_underscore() {
+        assert_eq!(gelf_key("rtc_id"), "_rtc_id");
+    }
+
+    #[test]
+    fn gelf_key_renames_id_to_avoid_the_reserved_gelf_field() {
+        assert_eq!(gelf_key("id"), "_id_field");
+    }
+
+    #[test]
+    fn render_syslog_frame_computes_pri_from_facility_and_severity() {
+        let frame = render_syslog_frame(
+            "ERRO",
+            "host",
+            "app",
+            &SourceWithTags::Core(CoreTags::default()),
+            "2024-01-01T00:00:00Z",
+            "boom",
+        );
+
+        // local0 (16) * 8 + ERRO severity (3) = 131.
+        assert!(frame.starts_with("<131>1 "));
+    }
+
+    #[test]
+    fn render_syslog_frame_falls_back_to_nilvalue_structured_data() {
+        let frame = render_syslog_frame(
+            "INFO",
+            "host",
+            "app",
+            &SourceWithTags::Core(CoreTags::default()),
+            "2024-01-01T00:00:00Z",
+            "hello",
+        );
+
+        assert!(frame.contains(" - hello"));
+    }
+
+    #[test]
+    fn render_syslog_frame_escapes_embedded_newlines_in_msg() {
+        let frame = render_syslog_frame(
+            "INFO",
+            "host",
+            "app",
+            &SourceWithTags::Core(CoreTags::default()),
+            "2024-01-01T00:00:00Z",
+            "line one\nline two",
+        );
+
+        assert!(frame.ends_with("line one\\nline two"));
+    }
+}
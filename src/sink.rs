@@ -0,0 +1,356 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::net::{TcpStream, UdpSocket};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use serde_json::Value as JsonValue;
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub(crate) trait Sink: Send {
+    fn write(&mut self, line: &str);
+
+    // Called by the worker once per report interval (when recv_timeout elapses
+    // with no new line, or when the interval has passed under steady traffic).
+    // Sinks that batch (e.g. HttpSink) use this to drain a
+    // partial batch on the flush timer instead of waiting for the next line.
+    fn tick(&mut self) {}
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Default)]
+pub(crate) struct StdoutSink;
+
+impl Sink for StdoutSink {
+    fn write(&mut self, line: &str) {
+        println!("{}", line);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub(crate) struct FileSink {
+    path: PathBuf,
+    max_bytes: u64,
+    file: File,
+    written: u64,
+}
+
+impl FileSink {
+    pub(crate) fn new(path: PathBuf, max_bytes: u64) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            max_bytes,
+            file,
+            written,
+        })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let rotated_path = self.path.with_file_name(format!(
+            "{}.1",
+            self.path.file_name().unwrap_or_default().to_string_lossy()
+        ));
+
+        fs::rename(&self.path, rotated_path)?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Sink for FileSink {
+    fn write(&mut self, line: &str) {
+        if self.max_bytes > 0 && self.written >= self.max_bytes {
+            if let Err(err) = self.rotate() {
+                eprintln!("Failed to rotate log file {:?}: {}", self.path, err);
+            }
+        }
+
+        let mut buf = String::with_capacity(line.len() + 1);
+        buf.push_str(line);
+        buf.push('\n');
+
+        match self.file.write_all(buf.as_bytes()) {
+            Ok(()) => self.written += buf.len() as u64,
+            Err(err) => eprintln!("Failed to write to log file {:?}: {}", self.path, err),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+struct Backoff {
+    attempt: u32,
+    base: Duration,
+    max: Duration,
+}
+
+impl Backoff {
+    fn new() -> Self {
+        Self {
+            attempt: 0,
+            base: Duration::from_millis(200),
+            max: Duration::from_secs(30),
+        }
+    }
+
+    fn next_delay(&mut self) -> Duration {
+        let factor = 1u32.checked_shl(self.attempt.min(10)).unwrap_or(u32::MAX);
+        let delay = self
+            .base
+            .checked_mul(factor)
+            .unwrap_or(self.max)
+            .min(self.max);
+        self.attempt += 1;
+        delay
+    }
+
+    fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub(crate) struct TcpSink {
+    addr: String,
+    stream: Option<TcpStream>,
+    backoff: Backoff,
+    next_attempt_at: Instant,
+}
+
+impl TcpSink {
+    pub(crate) fn new(addr: String) -> Self {
+        Self {
+            addr,
+            stream: None,
+            backoff: Backoff::new(),
+            next_attempt_at: Instant::now(),
+        }
+    }
+
+    fn connect(&mut self) {
+        if Instant::now() < self.next_attempt_at {
+            return;
+        }
+
+        match TcpStream::connect(&self.addr) {
+            Ok(stream) => {
+                self.stream = Some(stream);
+                self.backoff.reset();
+            }
+            Err(err) => {
+                eprintln!("Failed to connect TCP sink to {}: {}", self.addr, err);
+                self.next_attempt_at = Instant::now() + self.backoff.next_delay();
+            }
+        }
+    }
+}
+
+impl Sink for TcpSink {
+    fn write(&mut self, line: &str) {
+        if self.stream.is_none() {
+            self.connect();
+        }
+
+        let mut failed = false;
+
+        if let Some(stream) = self.stream.as_mut() {
+            if stream
+                .write_all(line.as_bytes())
+                .and_then(|_| stream.write_all(b"\n"))
+                .is_err()
+            {
+                failed = true;
+            }
+        }
+
+        if failed {
+            self.stream = None;
+            self.next_attempt_at = Instant::now() + self.backoff.next_delay();
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub(crate) struct UdpSink {
+    addr: String,
+    socket: Option<UdpSocket>,
+    backoff: Backoff,
+    next_attempt_at: Instant,
+}
+
+impl UdpSink {
+    pub(crate) fn new(addr: String) -> Self {
+        Self {
+            addr,
+            socket: None,
+            backoff: Backoff::new(),
+            next_attempt_at: Instant::now(),
+        }
+    }
+
+    fn connect(&mut self) {
+        if Instant::now() < self.next_attempt_at {
+            return;
+        }
+
+        let result = UdpSocket::bind("0.0.0.0:0").and_then(|socket| {
+            socket.connect(&self.addr)?;
+            Ok(socket)
+        });
+
+        match result {
+            Ok(socket) => {
+                self.socket = Some(socket);
+                self.backoff.reset();
+            }
+            Err(err) => {
+                eprintln!("Failed to connect UDP sink to {}: {}", self.addr, err);
+                self.next_attempt_at = Instant::now() + self.backoff.next_delay();
+            }
+        }
+    }
+}
+
+impl Sink for UdpSink {
+    fn write(&mut self, line: &str) {
+        if self.socket.is_none() {
+            self.connect();
+        }
+
+        let mut failed = false;
+
+        if let Some(socket) = self.socket.as_ref() {
+            // Matches TcpSink's line-delimited contract so a receiver that
+            // reassembles the stream (flat/GELF) can split records; a single
+            // send() keeps the delimiter in the same datagram as the line.
+            let mut buf = String::with_capacity(line.len() + 1);
+            buf.push_str(line);
+            buf.push('\n');
+
+            if socket.send(buf.as_bytes()).is_err() {
+                failed = true;
+            }
+        }
+
+        if failed {
+            self.socket = None;
+            self.next_attempt_at = Instant::now() + self.backoff.next_delay();
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub(crate) struct HttpSink {
+    url: String,
+    batch_size: usize,
+    flush_interval: Duration,
+    max_buffer_size: usize,
+    buffer: VecDeque<String>,
+    dropped: u64,
+    last_flush: Instant,
+    backoff: Backoff,
+    next_attempt_at: Instant,
+    agent: ureq::Agent,
+}
+
+impl HttpSink {
+    pub(crate) fn new(
+        url: String,
+        batch_size: usize,
+        flush_interval: Duration,
+        max_buffer_size: usize,
+    ) -> Self {
+        Self {
+            url,
+            batch_size,
+            flush_interval,
+            max_buffer_size,
+            buffer: VecDeque::new(),
+            dropped: 0,
+            last_flush: Instant::now(),
+            backoff: Backoff::new(),
+            next_attempt_at: Instant::now(),
+            agent: ureq::Agent::new(),
+        }
+    }
+
+    fn should_flush(&self) -> bool {
+        self.buffer.len() >= self.batch_size || self.last_flush.elapsed() >= self.flush_interval
+    }
+
+    fn flush(&mut self) {
+        if self.buffer.is_empty() || Instant::now() < self.next_attempt_at {
+            return;
+        }
+
+        // Lines are JSON objects for the flat/GELF formats, but plain text frames
+        // for syslog; fall back to shipping those as JSON strings instead of
+        // dropping them.
+        let batch: Vec<JsonValue> = self
+            .buffer
+            .iter()
+            .map(|line| {
+                serde_json::from_str(line).unwrap_or_else(|_err| JsonValue::from(line.as_str()))
+            })
+            .collect();
+
+        match self.agent.post(&self.url).send_json(JsonValue::from(batch)) {
+            Ok(_response) => {
+                self.buffer.clear();
+                self.last_flush = Instant::now();
+                self.backoff.reset();
+            }
+            Err(err) => {
+                eprintln!("Failed to flush HTTP sink batch to {}: {}", self.url, err);
+                self.next_attempt_at = Instant::now() + self.backoff.next_delay();
+            }
+        }
+    }
+}
+
+impl Sink for HttpSink {
+    fn write(&mut self, line: &str) {
+        // While the endpoint is down `flush()` leaves the buffer untouched, so
+        // without a cap a sustained outage would grow it without bound. Drop
+        // the oldest buffered line to make room, same as the channel's
+        // drop_oldest overflow policy.
+        if self.buffer.len() >= self.max_buffer_size {
+            self.buffer.pop_front();
+            self.dropped += 1;
+
+            if self.dropped == 1 || self.dropped % 100 == 0 {
+                eprintln!(
+                    "HTTP sink buffer for {} is full, dropped {} line(s) so far",
+                    self.url, self.dropped
+                );
+            }
+        }
+
+        self.buffer.push_back(line.to_owned());
+
+        if self.should_flush() {
+            self.flush();
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.should_flush() {
+            self.flush();
+        }
+    }
+}
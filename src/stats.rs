@@ -0,0 +1,97 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde_json::{json, Value as JsonValue};
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub(crate) const LEVEL_ERRO: u8 = 0;
+pub(crate) const LEVEL_WARN: u8 = 1;
+pub(crate) const LEVEL_INFO: u8 = 2;
+
+pub(crate) fn level_rank(level: &str) -> u8 {
+    match level {
+        "ERRO" => LEVEL_ERRO,
+        "WARN" => LEVEL_WARN,
+        _ => LEVEL_INFO,
+    }
+}
+
+pub(crate) fn level_name(rank: u8) -> &'static str {
+    match rank {
+        LEVEL_ERRO => "ERRO",
+        LEVEL_WARN => "WARN",
+        _ => "INFO",
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Default)]
+pub(crate) struct Stats {
+    received: AtomicU64,
+    emitted: AtomicU64,
+    dropped: AtomicU64,
+    filtered: AtomicU64,
+    serialization_errors: AtomicU64,
+    received_core: AtomicU64,
+    received_conference: AtomicU64,
+    received_unknown: AtomicU64,
+}
+
+impl Stats {
+    pub(crate) fn record_received(&self) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_emitted(&self) {
+        self.emitted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    // Messages below the configured min_level are filtered by design, not
+    // dropped due to overflow, so they get their own counter rather than
+    // inflating the overflow metric the drop-rate warning reads from.
+    pub(crate) fn record_filtered(&self) {
+        self.filtered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // A line that failed to serialize into the configured output format is
+    // neither overflow nor a level filter, so it gets its own counter rather
+    // than inflating the overflow metric the drop-rate warning reads from.
+    pub(crate) fn record_serialization_error(&self) {
+        self.serialization_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_source(&self, source: &str) {
+        let counter = match source {
+            "core" => &self.received_core,
+            "conference" => &self.received_conference,
+            _ => &self.received_unknown,
+        };
+
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> JsonValue {
+        json!({
+            "result": "ok",
+            "received": self.received.load(Ordering::Relaxed),
+            "emitted": self.emitted.load(Ordering::Relaxed),
+            "dropped": self.dropped.load(Ordering::Relaxed),
+            "filtered": self.filtered.load(Ordering::Relaxed),
+            "serialization_errors": self.serialization_errors.load(Ordering::Relaxed),
+            "per_source": {
+                "core": self.received_core.load(Ordering::Relaxed),
+                "conference": self.received_conference.load(Ordering::Relaxed),
+                "unknown": self.received_unknown.load(Ordering::Relaxed),
+            },
+        })
+    }
+}